@@ -12,19 +12,23 @@
 //! with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use argh::FromArgs;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{WalkBuilder, WalkState};
 use indicatif::{HumanBytes, ProgressBar};
 use owo_colors::OwoColorize;
-use serde::Deserialize;
-use snafu::{OptionExt, ResultExt, Whatever};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use snafu::{FromString, OptionExt, ResultExt, Whatever};
 use std::{
     cmp, env, fs,
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
     iter,
     path::{Path, PathBuf},
     process,
-    time::Duration,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
 };
-use walkdir::WalkDir;
+use xz2::write::XzEncoder;
 
 // swim forces this (for now)
 const BUILD_DIRECTORY_PATH: &str = "build";
@@ -33,8 +37,71 @@ const DEFAULT_CONFIG_FILE_NAME: &str = "swim-clean-all.toml";
 
 #[derive(Deserialize)]
 struct Config {
-    /// Directories to skip when traversing.
+    /// Directories to skip when traversing. A bare name (e.g. `vendor`)
+    /// matches any directory with that name anywhere under the search root;
+    /// a path with a separator (e.g. `build/tmp`) is resolved relative to
+    /// the current directory; glob patterns (e.g. `*/third_party/*`) are
+    /// matched directly.
     skip: Option<Vec<PathBuf>>,
+
+    /// A bare name, literal path, or glob pattern a project's path must
+    /// match/be under to be considered for cleaning. Same bare-name-vs-path
+    /// rules as `skip`.
+    only: Option<Vec<PathBuf>>,
+
+    /// Whether to ignore `.gitignore`/`.ignore`/global git excludes while
+    /// traversing.
+    no_ignore: Option<bool>,
+}
+
+/// Output representation for cleaning results.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    /// Colored, spinner-backed, human-readable output (the default).
+    Human,
+    /// A single machine-readable JSON document on stdout.
+    Json,
+}
+
+fn parse_format(value: &str) -> Result<OutputFormat, String> {
+    match value {
+        "human" => Ok(OutputFormat::Human),
+        "json" => Ok(OutputFormat::Json),
+        _ => Err(format!("unknown format `{value}`; expected human or json")),
+    }
+}
+
+/// What happened (or would happen) to a single project.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum ProjectAction {
+    Cleaned,
+    Archived,
+    Skipped,
+    WouldClean,
+    WouldArchive,
+}
+
+/// A machine-readable record of the outcome for a single project, emitted
+/// under `--format json`.
+#[derive(Serialize)]
+struct ProjectReport {
+    path: PathBuf,
+    build_size_bytes: u64,
+    action: ProjectAction,
+}
+
+/// The machine-readable summary emitted under `--format json`.
+#[derive(Serialize)]
+struct Summary {
+    total_bytes: u64,
+    project_count: usize,
+}
+
+#[derive(Serialize)]
+struct Report {
+    projects: Vec<ProjectReport>,
+    summary: Summary,
 }
 
 /// Tries to read a config file from `XDG_CONFIG_HOME`, then from the operating
@@ -81,10 +148,20 @@ fn read_config(
 /// specified criteria
 #[derive(FromArgs)]
 struct Opts {
-    /// directories to skip when traversing
+    /// directories to skip when traversing; a bare name (e.g. `vendor`)
+    /// matches any directory with that name anywhere under the search root,
+    /// a path with a separator is resolved relative to the current
+    /// directory, and a glob pattern (e.g. `*/third_party/*`) is matched
+    /// directly
     #[argh(option)]
     skip: Vec<PathBuf>,
 
+    /// restrict cleaning to projects matching this bare name, literal path,
+    /// or glob pattern; same bare-name-vs-path rules as --skip; may be
+    /// given multiple times
+    #[argh(option)]
+    only: Vec<PathBuf>,
+
     /// maximum depth search limit; defaults to 100
     #[argh(option, default = "100")]
     max_depth: usize,
@@ -97,6 +174,51 @@ struct Opts {
     #[argh(switch)]
     ignore_config: bool,
 
+    /// do not respect .gitignore, .ignore, or global git excludes while
+    /// traversing
+    #[argh(switch)]
+    no_ignore: bool,
+
+    /// compress each project's build/ into a .tar.xz before removing it,
+    /// rather than deleting it outright
+    #[argh(switch)]
+    archive: bool,
+
+    /// central directory to write archives into; defaults to a sibling
+    /// build.tar.xz next to each project's build/
+    #[argh(option)]
+    archive_dir: Option<PathBuf>,
+
+    /// xz compression level (0-9); defaults to 9, which uses a 64 MiB
+    /// dictionary window for the best ratios
+    #[argh(option, default = "9", from_str_fn(parse_compression_level))]
+    compression_level: u32,
+
+    /// skip projects whose most recent commit is newer than this duration,
+    /// e.g. `2h`, `30m`, `1d`
+    #[argh(option, from_str_fn(parse_duration))]
+    min_age: Option<Duration>,
+
+    /// refuse to clean projects with uncommitted git changes
+    #[argh(switch)]
+    skip_dirty: bool,
+
+    /// clean all discovered projects without prompting for confirmation
+    #[argh(switch, short = 'y')]
+    yes: bool,
+
+    /// report what would be cleaned without touching the filesystem
+    #[argh(switch)]
+    dry_run: bool,
+
+    /// output format: `human` (default) or `json`
+    #[argh(
+        option,
+        default = "OutputFormat::Human",
+        from_str_fn(parse_format)
+    )]
+    format: OutputFormat,
+
     /// print debugging information
     #[argh(switch)]
     verbose: bool,
@@ -144,6 +266,12 @@ fn parse_opts() -> Result<Opts, Whatever> {
                     if let Some(skip) = config.skip {
                         opts.skip.extend(skip);
                     }
+                    if let Some(only) = config.only {
+                        opts.only.extend(only);
+                    }
+                    if let Some(no_ignore) = config.no_ignore {
+                        opts.no_ignore = opts.no_ignore || no_ignore;
+                    }
                 }
             }
 
@@ -166,6 +294,137 @@ fn parse_opts() -> Result<Opts, Whatever> {
     }
 }
 
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    humantime::parse_duration(value).map_err(|error| error.to_string())
+}
+
+fn parse_compression_level(value: &str) -> Result<u32, String> {
+    let level = value
+        .parse::<u32>()
+        .map_err(|error| format!("invalid compression level `{value}`: {error}"))?;
+    if level > 9 {
+        return Err(format!(
+            "compression level {level} out of range; expected 0-9"
+        ));
+    }
+    Ok(level)
+}
+
+/// Git-derived safety information about a project, or `None` if it isn't
+/// inside a git repository.
+struct GitInfo {
+    /// Whether `project`'s own subtree has uncommitted changes (not the
+    /// whole repository — a monorepo neighbor's edits shouldn't count).
+    dirty: bool,
+
+    /// How long ago the most recent commit touching `project`'s subtree
+    /// was made, if any, walking first-parent history from `HEAD`.
+    age: Option<Duration>,
+}
+
+/// The path of `project` relative to the root of the git worktree containing
+/// it, or `None` if `project` isn't inside a worktree we can determine.
+fn relative_to_worktree(repo: &gix::Repository, project: &Path) -> Option<PathBuf> {
+    let work_dir = repo.work_dir()?;
+    let work_dir = canonicalize(work_dir).ok()?;
+    project.strip_prefix(work_dir).ok().map(Path::to_path_buf)
+}
+
+/// Whether any uncommitted change under `relative` (a path relative to the
+/// worktree root) exists, without considering changes elsewhere in the repo.
+fn is_path_dirty(repo: &gix::Repository, relative: &Path) -> bool {
+    let Ok(status) = repo.status(gix::progress::Discard) else {
+        return false;
+    };
+    let patterns: Vec<gix::bstr::BString> = if relative.as_os_str().is_empty() {
+        Vec::new()
+    } else {
+        vec![format!("{}/**", relative.to_string_lossy()).into()]
+    };
+    match status.into_iter(patterns) {
+        Ok(mut entries) => entries.any(|entry| entry.is_ok()),
+        Err(_) => false,
+    }
+}
+
+/// Walks first-parent history from `HEAD` to find the most recent commit
+/// whose tree differs from its parent's at `relative`, mirroring
+/// `git log -1 --first-parent -- <relative>`.
+fn last_commit_time_for_path(
+    repo: &gix::Repository,
+    relative: &Path,
+) -> Option<Duration> {
+    // `lookup_entry_by_path("")` has no named entry to look up — the project
+    // itself is the tree root in this case, so compare the whole tree's id
+    // instead of a named entry's id within it.
+    let tree_id_at = |commit: &gix::Commit<'_>| -> Option<gix::ObjectId> {
+        if relative.as_os_str().is_empty() {
+            Some(commit.tree_id().ok()?.detach())
+        } else {
+            commit
+                .tree()
+                .ok()?
+                .lookup_entry_by_path(relative)
+                .ok()
+                .flatten()
+                .map(|entry| entry.object_id())
+        }
+    };
+
+    let mut commit = repo.head_commit().ok()?;
+    loop {
+        let entry_id = tree_id_at(&commit);
+
+        let parent_id = commit.parent_ids().next();
+        let parent_commit = parent_id
+            .and_then(|id| id.object().ok())
+            .and_then(|object| object.try_into_commit().ok());
+        let parent_entry_id =
+            parent_commit.as_ref().and_then(|parent| tree_id_at(parent));
+
+        if entry_id != parent_entry_id {
+            let committed_at = SystemTime::UNIX_EPOCH
+                + Duration::from_secs(commit.time().ok()?.seconds.max(0) as u64);
+            return SystemTime::now().duration_since(committed_at).ok();
+        }
+
+        commit = parent_commit?;
+    }
+}
+
+fn git_info(project: &Path) -> Option<GitInfo> {
+    let repo = gix::discover(project).ok()?;
+    let relative = relative_to_worktree(&repo, project).unwrap_or_default();
+    let dirty = is_path_dirty(&repo, &relative);
+    let age = last_commit_time_for_path(&repo, &relative);
+    Some(GitInfo { dirty, age })
+}
+
+/// Reports that no projects were found, either as a human message or as an
+/// empty JSON report, depending on `json_output`.
+fn emit_empty_report(
+    json_output: bool,
+    message: &str,
+) -> Result<(), Whatever> {
+    if json_output {
+        let report = Report {
+            projects: Vec::new(),
+            summary: Summary {
+                total_bytes: 0,
+                project_count: 0,
+            },
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report)
+                .whatever_context("Failed to serialize JSON report")?
+        );
+    } else {
+        println!("{message}");
+    }
+    Ok(())
+}
+
 fn canonicalize(path: &Path) -> io::Result<PathBuf> {
     let mut path = path.to_path_buf();
     if path.starts_with("~") {
@@ -178,11 +437,118 @@ fn canonicalize(path: &Path) -> io::Result<PathBuf> {
     fs::canonicalize(path)
 }
 
+/// Whether `pattern` looks like a glob rather than a literal path, i.e.
+/// whether it contains any glob metacharacters.
+fn is_glob_pattern(pattern: &Path) -> bool {
+    pattern
+        .to_string_lossy()
+        .contains(['*', '?', '[', '{'])
+}
+
+/// Whether `pattern` is a single bare component with no path separator
+/// (e.g. `vendor` rather than `build/vendor`), and so should match any
+/// directory with that name rather than one specific, cwd-relative path.
+fn is_bare_name(pattern: &Path) -> bool {
+    pattern.components().count() == 1
+}
+
+/// Compiles `patterns` into a single [`GlobSet`].
+fn build_globset(patterns: &[PathBuf]) -> Result<GlobSet, Whatever> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.to_string_lossy();
+        builder.add(Glob::new(&pattern).whatever_context(format!(
+            "Failed to parse glob pattern {pattern}"
+        ))?);
+    }
+    builder
+        .build()
+        .whatever_context("Failed to build glob pattern set")
+}
+
+/// Compresses `project`'s `build/` directory into a `.tar.xz` archive,
+/// either inside the project next to its `build/` or under `archive_dir`,
+/// and returns the
+/// archive's path and its size on disk.
+fn archive_build_directory(
+    project: &Path,
+    archive_dir: Option<&Path>,
+    compression_level: u32,
+) -> Result<(PathBuf, u64), Whatever> {
+    let archive_path = match archive_dir {
+        Some(archive_dir) => {
+            fs::create_dir_all(archive_dir).whatever_context(format!(
+                "Failed to create archive directory {}",
+                archive_dir.to_string_lossy()
+            ))?;
+            let archive_name =
+                project.to_string_lossy().replace(['/', '\\'], "_");
+            archive_dir.join(format!("{archive_name}.build.tar.xz"))
+        }
+        None => project.join("build.tar.xz"),
+    };
+
+    let archive_file = fs::File::create(&archive_path).whatever_context(
+        format!(
+            "Failed to create archive file {}",
+            archive_path.to_string_lossy()
+        ),
+    )?;
+    let encoder = XzEncoder::new(archive_file, compression_level);
+    let mut tar_builder = tar::Builder::new(encoder);
+    tar_builder
+        .append_dir_all(BUILD_DIRECTORY_PATH, project.join(BUILD_DIRECTORY_PATH))
+        .whatever_context(format!(
+            "Failed to archive build directory for project at {}",
+            project.to_string_lossy()
+        ))?;
+    tar_builder
+        .into_inner()
+        .whatever_context("Failed to finish writing tar archive")?
+        .finish()
+        .whatever_context("Failed to finish xz compression")?;
+
+    let archive_size = fs::metadata(&archive_path)
+        .whatever_context(format!(
+            "Failed to stat archive file {}",
+            archive_path.to_string_lossy()
+        ))?
+        .len();
+
+    Ok((archive_path, archive_size))
+}
+
+/// Applies `apply` (an `OwoColorize` chain) to `text`, unless `plain` is set,
+/// in which case `text` is returned untouched. Used instead of
+/// `owo_colors::set_override`, which needs the crate's `supports-colors`
+/// feature.
+fn styled(plain: bool, text: String, apply: impl FnOnce(&str) -> String) -> String {
+    if plain {
+        text
+    } else {
+        apply(&text)
+    }
+}
+
 #[snafu::report]
 fn main() -> Result<(), Whatever> {
     let opts = parse_opts()
         .whatever_context("Failed to parse command line arguments")?;
 
+    let json_output = matches!(opts.format, OutputFormat::Json);
+    let plain_output = json_output || !io::stdout().is_terminal();
+
+    // `--format` is purely a rendering concern, not consent to mutate the
+    // filesystem — without a TTY prompt to fall back on, the user must be
+    // explicit about what should happen.
+    if json_output && !opts.yes && !opts.dry_run {
+        return Err(Whatever::without_source(
+            "--format json has no interactive prompt to fall back on; pass \
+             --yes to clean non-interactively or --dry-run to only report"
+                .to_owned(),
+        ));
+    }
+
     let search_root =
         canonicalize(&opts.search_root).whatever_context(format!(
             "Failed to canonicalize search root {}",
@@ -190,163 +556,437 @@ fn main() -> Result<(), Whatever> {
         ))?;
 
     let mut skipped_directories = Vec::with_capacity(opts.skip.len());
+    let mut skipped_patterns = Vec::with_capacity(opts.skip.len());
     for skipped_directory in opts.skip {
-        skipped_directories.push(
-            canonicalize(&skipped_directory).whatever_context(format!(
-                "Failed to canonicalize skipped directory {}",
-                skipped_directory.to_string_lossy()
-            ))?,
-        );
+        if is_glob_pattern(&skipped_directory) {
+            skipped_patterns.push(skipped_directory);
+        } else if is_bare_name(&skipped_directory) {
+            skipped_patterns.push(Path::new("**").join(&skipped_directory));
+        } else {
+            skipped_directories.push(
+                canonicalize(&skipped_directory).whatever_context(format!(
+                    "Failed to canonicalize skipped directory {}",
+                    skipped_directory.to_string_lossy()
+                ))?,
+            );
+        }
     }
+    let skip_globset = build_globset(&skipped_patterns)
+        .whatever_context("Failed to compile --skip glob patterns")?;
+
+    let mut only_directories = Vec::with_capacity(opts.only.len());
+    let mut only_patterns = Vec::with_capacity(opts.only.len());
+    for only_entry in opts.only {
+        if is_glob_pattern(&only_entry) {
+            only_patterns.push(only_entry);
+        } else if is_bare_name(&only_entry) {
+            only_patterns.push(Path::new("**").join(&only_entry));
+        } else {
+            only_directories.push(
+                canonicalize(&only_entry).whatever_context(format!(
+                    "Failed to canonicalize --only directory {}",
+                    only_entry.to_string_lossy()
+                ))?,
+            );
+        }
+    }
+    let only_globset = build_globset(&only_patterns)
+        .whatever_context("Failed to compile --only glob patterns")?;
+    let only_restricted = !only_directories.is_empty() || !only_globset.is_empty();
 
-    let spinner =
-        ProgressBar::new_spinner().with_message("Scanning for swim projects");
+    let spinner = if plain_output {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new_spinner().with_message("Scanning for swim projects")
+    };
     spinner.enable_steady_tick(Duration::from_millis(100));
 
     let minimum_components_to_show =
         search_root.components().collect::<Vec<_>>().len();
 
-    let projects = WalkDir::new(search_root)
-        .max_depth(opts.max_depth)
-        .into_iter()
-        .filter_entry(|entry| {
-            !skipped_directories.iter().any(|skipped_directory| {
-                entry.path().starts_with(skipped_directory)
-            })
-        })
-        .filter_map(|entry| entry.ok())
-        .inspect(|entry| {
-            let components = entry.path().components().collect::<Vec<_>>();
-            let display_components = cmp::min(
-                minimum_components_to_show + 2,
-                if entry.path().is_dir() {
-                    components.len()
-                } else {
-                    components.len() - 1
-                },
-            );
-            let display_directory = components
-                .into_iter()
-                .take(display_components)
-                .map(|component| {
-                    let component =
-                        component.as_os_str().to_string_lossy().to_string();
-                    if component.starts_with("/") {
-                        component
+    let projects = Arc::new(Mutex::new(Vec::new()));
+    WalkBuilder::new(&search_root)
+        .max_depth(Some(opts.max_depth))
+        // We've always walked into hidden directories; only the
+        // .gitignore/.ignore/global-exclude honoring is new and togglable.
+        .hidden(false)
+        .git_ignore(!opts.no_ignore)
+        .git_global(!opts.no_ignore)
+        .git_exclude(!opts.no_ignore)
+        .ignore(!opts.no_ignore)
+        .build_parallel()
+        .run(|| {
+            let spinner = spinner.clone();
+            let skipped_directories = &skipped_directories;
+            let skip_globset = &skip_globset;
+            let only_directories = &only_directories;
+            let only_globset = &only_globset;
+            let projects = Arc::clone(&projects);
+            Box::new(move |entry| {
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+
+                if skipped_directories.iter().any(|skipped_directory| {
+                    entry.path().starts_with(skipped_directory)
+                }) || skip_globset.is_match(entry.path())
+                {
+                    return WalkState::Skip;
+                }
+
+                let components = entry.path().components().collect::<Vec<_>>();
+                let display_components = cmp::min(
+                    minimum_components_to_show + 2,
+                    if entry.path().is_dir() {
+                        components.len()
                     } else {
-                        format!("{}/", component)
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join("");
-            spinner.set_message(format!(
-                "Scanning for cleanable swim projects {}{}{}",
-                "[".bold(),
-                display_directory.bold(),
-                "]".bold()
-            ));
-        })
-        .filter(|entry| {
-            entry.path().is_dir()
-                && entry.path().join("swim.toml").exists()
-                && entry.path().join(BUILD_DIRECTORY_PATH).exists()
-        })
-        .collect::<Vec<_>>();
+                        components.len() - 1
+                    },
+                );
+                let display_directory = components
+                    .into_iter()
+                    .take(display_components)
+                    .map(|component| {
+                        let component =
+                            component.as_os_str().to_string_lossy().to_string();
+                        if component.starts_with("/") {
+                            component
+                        } else {
+                            format!("{}/", component)
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("");
+                spinner.set_message(format!(
+                    "Scanning for cleanable swim projects {}{}{}",
+                    "[".bold(),
+                    display_directory.bold(),
+                    "]".bold()
+                ));
+
+                if entry.path().is_dir()
+                    && entry.path().join("swim.toml").exists()
+                    && entry.path().join(BUILD_DIRECTORY_PATH).exists()
+                    && (!only_restricted
+                        || only_directories.iter().any(|only_directory| {
+                            entry.path().starts_with(only_directory)
+                        })
+                        || only_globset.is_match(entry.path()))
+                {
+                    projects.lock().unwrap().push(entry.into_path());
+                }
+
+                WalkState::Continue
+            })
+        });
 
     spinner.finish_and_clear();
 
+    let mut projects = Arc::try_unwrap(projects)
+        .expect("all worker threads have joined by now")
+        .into_inner()
+        .unwrap();
+    // Worker threads discover projects in a nondeterministic order, so sort
+    // for a stable prompting order across runs.
+    projects.sort();
+
     if projects.is_empty() {
-        println!(
-            "No cleanable swim projects found in {}",
-            opts.search_root.to_string_lossy()
-        );
+        emit_empty_report(
+            json_output,
+            &format!(
+                "No cleanable swim projects found in {}",
+                opts.search_root.to_string_lossy()
+            ),
+        )?;
         return Ok(());
     }
 
-    let mut project_build_sizes = vec![];
-    for project in &projects {
-        project_build_sizes.push(
-            fs_extra::dir::get_size(project.path().join(BUILD_DIRECTORY_PATH))
-                .whatever_context(format!(
-                    "Failed to get size of directory {}",
-                    project.path().to_string_lossy()
-                ))?,
-        );
+    let project_build_sizes = projects
+        .par_iter()
+        .map(|project| fs_extra::dir::get_size(project.join(BUILD_DIRECTORY_PATH)))
+        .collect::<Result<Vec<_>, _>>()
+        .whatever_context("Failed to get size of a project's build directory")?;
+
+    let project_git_info =
+        projects.par_iter().map(|project| git_info(project)).collect::<Vec<_>>();
+
+    let mut kept_projects = Vec::with_capacity(projects.len());
+    let mut kept_sizes = Vec::with_capacity(projects.len());
+    let mut kept_git_info = Vec::with_capacity(projects.len());
+    let mut reports = Vec::new();
+    for ((project, size), info) in
+        iter::zip(iter::zip(projects, project_build_sizes), project_git_info)
+    {
+        let skip_reason = info.as_ref().and_then(|info| {
+            if opts.skip_dirty && info.dirty {
+                Some("dirty".to_owned())
+            } else if let (Some(min_age), Some(age)) = (opts.min_age, info.age)
+            {
+                (age < min_age).then(|| {
+                    format!(
+                        "modified {} ago",
+                        humantime::format_duration(Duration::from_secs(age.as_secs()))
+                    )
+                })
+            } else {
+                None
+            }
+        });
+
+        if let Some(reason) = skip_reason {
+            if !json_output {
+                println!(
+                    "{}",
+                    styled(
+                        plain_output,
+                        format!("Skipped {} ({}).", project.to_string_lossy(), reason),
+                        |s| s.dimmed().to_string()
+                    )
+                );
+            }
+            reports.push(ProjectReport {
+                path: project,
+                build_size_bytes: size,
+                action: ProjectAction::Skipped,
+            });
+        } else {
+            kept_projects.push(project);
+            kept_sizes.push(size);
+            kept_git_info.push(info);
+        }
     }
+    let projects = kept_projects;
+    let project_build_sizes = kept_sizes;
 
-    println!(
-        "{}",
-        format!(
-            "{} cleanable swim project{} found (totalling {} potential savings)",
-            projects.len(),
-            if projects.len() == 1 { "" } else { "s" },
-            HumanBytes(project_build_sizes.iter().sum())
-        )
-        .bold()
-        .green()
-    );
-    println!();
+    if projects.is_empty() {
+        if json_output {
+            let total_bytes = reports.iter().map(|r| r.build_size_bytes).sum();
+            let report = Report {
+                summary: Summary {
+                    total_bytes,
+                    project_count: reports.len(),
+                },
+                projects: reports,
+            };
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report)
+                    .whatever_context("Failed to serialize JSON report")?
+            );
+        } else {
+            println!(
+                "No cleanable swim projects left after git safety filtering"
+            );
+        }
+        return Ok(());
+    }
 
-    let mut size_saved = 0;
-    for (project, potential_savings) in iter::zip(projects, project_build_sizes)
-    {
-        print!(
+    if !json_output {
+        println!(
             "{}",
-            format!(
-                "  Clean {}? ({}) [y/n] ",
-                project.path().to_string_lossy(),
-                HumanBytes(potential_savings)
+            styled(
+                plain_output,
+                format!(
+                    "{} cleanable swim project{} found (totalling {} potential savings)",
+                    projects.len(),
+                    if projects.len() == 1 { "" } else { "s" },
+                    HumanBytes(project_build_sizes.iter().sum())
+                ),
+                |s| s.bold().green().to_string()
             )
-            .bold()
-            .blue()
         );
+        println!();
+    }
+
+    let non_interactive = opts.yes || opts.dry_run;
+    let mut size_saved = 0;
+    let mut size_archived = 0;
+    for ((project, potential_savings), info) in iter::zip(
+        iter::zip(projects, project_build_sizes),
+        kept_git_info,
+    ) {
+        let annotation = match info {
+            Some(info) if info.dirty => " [dirty]".to_owned(),
+            Some(info) => info
+                .age
+                .map(|age| {
+                    format!(
+                        " [modified {} ago]",
+                        humantime::format_duration(Duration::from_secs(age.as_secs()))
+                    )
+                })
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+
+        let proceed = if non_interactive {
+            true
+        } else {
+            print!(
+                "{}",
+                styled(
+                    plain_output,
+                    format!(
+                        "  {} {}{}? ({}) [y/n] ",
+                        if opts.archive { "Archive" } else { "Clean" },
+                        project.to_string_lossy(),
+                        annotation,
+                        HumanBytes(potential_savings)
+                    ),
+                    |s| s.bold().blue().to_string()
+                )
+            );
 
-        io::stdout()
-            .flush()
-            .whatever_context("Failed to flush stdout to show cleanup CLI")?;
+            io::stdout().flush().whatever_context(
+                "Failed to flush stdout to show cleanup CLI",
+            )?;
 
-        let user_answer = io::stdin()
-            .lines()
-            .next()
-            .unwrap()
-            .whatever_context("Failed to read line from stdin")?;
+            let user_answer = io::stdin()
+                .lines()
+                .next()
+                .unwrap()
+                .whatever_context("Failed to read line from stdin")?;
 
-        crossterm::execute!(
-            io::stdout(),
-            crossterm::cursor::MoveToPreviousLine(1)
-        )
-        .whatever_context("Failed to move up one line")?;
+            crossterm::execute!(
+                io::stdout(),
+                crossterm::cursor::MoveToPreviousLine(1)
+            )
+            .whatever_context("Failed to move up one line")?;
+
+            matches!(user_answer.trim(), "y" | "Y" | "yes")
+        };
+
+        if !proceed {
+            if !json_output {
+                println!(
+                    "{}",
+                    styled(
+                        plain_output,
+                        format!(
+                            "Skipped {} ({}).",
+                            project.to_string_lossy(),
+                            HumanBytes(potential_savings)
+                        ),
+                        |s| s.dimmed().to_string()
+                    )
+                )
+            }
+            reports.push(ProjectReport {
+                path: project,
+                build_size_bytes: potential_savings,
+                action: ProjectAction::Skipped,
+            });
+            continue;
+        }
 
-        if matches!(user_answer.trim(), "y" | "Y" | "yes") {
-            fs::remove_dir_all(project.path().join(BUILD_DIRECTORY_PATH))
+        if opts.archive {
+            if opts.dry_run {
+                if !json_output {
+                    println!(
+                        "Would archive {} ({}).",
+                        project.to_string_lossy(),
+                        HumanBytes(potential_savings)
+                    );
+                }
+                size_saved += potential_savings;
+                reports.push(ProjectReport {
+                    path: project,
+                    build_size_bytes: potential_savings,
+                    action: ProjectAction::WouldArchive,
+                });
+            } else {
+                let (archive_path, archive_size) = archive_build_directory(
+                    &project,
+                    opts.archive_dir.as_deref(),
+                    opts.compression_level,
+                )?;
+                fs::remove_dir_all(project.join(BUILD_DIRECTORY_PATH))
+                    .whatever_context(format!(
+                        "Failed to remove build directory for project at {}",
+                        project.to_string_lossy()
+                    ))?;
+                if !json_output {
+                    println!(
+                        "Archived {} to {} ({} -> {}).",
+                        project.to_string_lossy(),
+                        archive_path.to_string_lossy(),
+                        HumanBytes(potential_savings),
+                        HumanBytes(archive_size)
+                    );
+                }
+                size_saved += potential_savings;
+                size_archived += archive_size;
+                reports.push(ProjectReport {
+                    path: project,
+                    build_size_bytes: potential_savings,
+                    action: ProjectAction::Archived,
+                });
+            }
+        } else if opts.dry_run {
+            if !json_output {
+                println!(
+                    "Would clean {} ({}).",
+                    project.to_string_lossy(),
+                    HumanBytes(potential_savings)
+                );
+            }
+            size_saved += potential_savings;
+            reports.push(ProjectReport {
+                path: project,
+                build_size_bytes: potential_savings,
+                action: ProjectAction::WouldClean,
+            });
+        } else {
+            fs::remove_dir_all(project.join(BUILD_DIRECTORY_PATH))
                 .whatever_context(format!(
                     "Failed to remove build directory for project at {}",
-                    project.path().to_string_lossy()
+                    project.to_string_lossy()
                 ))?;
-            println!(
-                "Cleaned {} ({}).",
-                project.path().to_string_lossy(),
-                HumanBytes(potential_savings)
-            );
-            size_saved += potential_savings;
-        } else {
-            println!(
-                "{}",
-                format!(
-                    "Skipped {} ({}).",
-                    project.path().to_string_lossy(),
+            if !json_output {
+                println!(
+                    "Cleaned {} ({}).",
+                    project.to_string_lossy(),
                     HumanBytes(potential_savings)
-                )
-                .dimmed()
-            )
+                );
+            }
+            size_saved += potential_savings;
+            reports.push(ProjectReport {
+                path: project,
+                build_size_bytes: potential_savings,
+                action: ProjectAction::Cleaned,
+            });
         }
     }
 
-    println!();
-    if size_saved > 0 {
-        println!("{} successfully cleaned", HumanBytes(size_saved));
+    if json_output {
+        let total_bytes = reports.iter().map(|r| r.build_size_bytes).sum();
+        let report = Report {
+            summary: Summary {
+                total_bytes,
+                project_count: reports.len(),
+            },
+            projects: reports,
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report)
+                .whatever_context("Failed to serialize JSON report")?
+        );
     } else {
-        println!("No projects cleaned");
+        println!();
+        if size_saved > 0 {
+            let verb = if opts.dry_run { "would be" } else { "successfully" };
+            println!("{} {} cleaned", HumanBytes(size_saved), verb);
+            if opts.archive && !opts.dry_run {
+                println!(
+                    "{} archived (compressed from {})",
+                    HumanBytes(size_archived),
+                    HumanBytes(size_saved)
+                );
+            }
+        } else {
+            println!("No projects cleaned");
+        }
     }
 
     Ok(())